@@ -1,3 +1,4 @@
+use crate::utils::{Compression, HashAlgorithm};
 use clap::Parser;
 use std::path::PathBuf;
 
@@ -9,11 +10,98 @@ pub struct Cli {
     #[arg(long = "if", value_name = "FILE")]
     pub input: Option<PathBuf>,
 
-    /// Write to FILE instead of stdout.
+    /// Write to FILE instead of stdout. May be given multiple times to fan
+    /// the input out to several outputs concurrently (e.g. cloning an image
+    /// to several drives at once).
     #[arg(long = "of", value_name = "FILE")]
-    pub output: Option<PathBuf>,
+    pub output: Vec<PathBuf>,
 
     /// Read and write up to BYTES bytes at a time (default: 512); overrides ibs and obs
     #[arg(long = "bs", default_value = "512")]
     pub block_size: usize,
+
+    /// Convert the file as specified.
+    #[arg(long = "conv", value_name = "CONV")]
+    pub conv: Option<Conv>,
+
+    /// Decompress the input while reading, regardless of what its extension
+    /// indicates (the input is otherwise sniffed from `.zst`/`.xz`/`.bz2`/`.gz`).
+    #[arg(long = "decompress", value_name = "CODEC")]
+    pub decompress: Option<Codec>,
+
+    /// Compress the output with CODEC while writing, regardless of what its
+    /// extension indicates.
+    #[arg(long = "compress", value_name = "CODEC")]
+    pub compress: Option<Codec>,
+
+    /// Skip BLOCKS `bs`-sized blocks at the start of the input before copying.
+    #[arg(long = "skip", value_name = "BLOCKS", default_value = "0")]
+    pub skip: u64,
+
+    /// Skip BLOCKS `bs`-sized blocks at the start of the output before writing.
+    #[arg(long = "seek", value_name = "BLOCKS", default_value = "0")]
+    pub seek: u64,
+
+    /// Copy only BLOCKS `bs`-sized blocks instead of the whole input.
+    #[arg(long = "count", value_name = "BLOCKS")]
+    pub count: Option<u64>,
+
+    /// Digest algorithms to compute over the copied data in one pass,
+    /// comma-separated (e.g. `--hash crc32,sha256`).
+    #[arg(long = "hash", value_name = "ALGO", value_delimiter = ',')]
+    pub hash: Vec<HashAlgo>,
+
+    /// After copying, re-read the written output and confirm its digest
+    /// matches the digest stored in FILE (its first word, as `sha256sum`
+    /// writes it) or the literal hex HASH.
+    #[arg(long = "verify", value_name = "FILE-or-HASH")]
+    pub verify: Option<String>,
+}
+
+/// Digest algorithms supported by `--hash`/`--verify`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum HashAlgo {
+    Crc32,
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl From<HashAlgo> for HashAlgorithm {
+    fn from(algo: HashAlgo) -> Self {
+        match algo {
+            HashAlgo::Crc32 => HashAlgorithm::Crc32,
+            HashAlgo::Md5 => HashAlgorithm::Md5,
+            HashAlgo::Sha1 => HashAlgorithm::Sha1,
+            HashAlgo::Sha256 => HashAlgorithm::Sha256,
+        }
+    }
+}
+
+/// Compression codecs supported for transparent input/output (de)compression.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Codec {
+    Zstd,
+    Xz,
+    Bzip2,
+    Gzip,
+}
+
+impl From<Codec> for Compression {
+    fn from(codec: Codec) -> Self {
+        match codec {
+            Codec::Zstd => Compression::Zstd,
+            Codec::Xz => Compression::Xz,
+            Codec::Bzip2 => Compression::Bzip2,
+            Codec::Gzip => Compression::Gzip,
+        }
+    }
+}
+
+/// Conversions that can be applied to the output while copying.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Conv {
+    /// Detect runs of zero bytes in the output and seek over them instead of
+    /// writing them, producing a sparse file.
+    Sparse,
 }
@@ -1,6 +1,11 @@
+pub mod dd;
 pub mod utils;
 
 pub use utils::{
-    InputSource, OutputSource, ProgressType, ThreadedCopyConfig, copy_with_callback, create_buffer,
-    get_progress_target, open_input_file, open_output_file, validate_special_device_combo,
+    check_and_handle_mount, check_verify_target, copy_with_callback, copy_with_callback_multi,
+    copy_with_progress, create_buffer, create_progress_bar, finish_pb_with_message,
+    get_progress_target, open_input_file, open_output_file, seek_output, skip_input,
+    validate_special_device_combo, verify_written_digest, Compression, DigestResult,
+    HashAlgorithm, InputSource, MultiCopyReport, OutputSource, ProgressType, TargetOutcome,
+    ThreadedCopyConfig,
 };
@@ -1,79 +1,189 @@
 use clap::Parser;
-use indicatif::{ProgressBar, ProgressStyle};
-use std::fs::{File, OpenOptions};
-use std::io::{self, Read, Write};
+use dd_rs::dd::{Cli, Conv, HashAlgo};
+use dd_rs::{
+    check_and_handle_mount, check_verify_target, copy_with_callback, copy_with_callback_multi,
+    create_progress_bar, finish_pb_with_message, get_progress_target, open_input_file,
+    open_output_file, seek_output, skip_input, validate_special_device_combo,
+    verify_written_digest, Compression, DigestResult, HashAlgorithm, TargetOutcome,
+    ThreadedCopyConfig,
+};
+use std::io;
 use std::path::PathBuf;
 use std::process;
 
-// A dd-like tool with a progress bar.
-#[derive(Parser, Debug)]
-#[clap(name = "dd", author, version, about, long_about = None)]
-struct Args {
-    /// The input file.
-    #[arg(short = 'i', long, value_name = "FILE")]
-    input: PathBuf,
-
-    /// The output file.
-    #[arg(short = 'o', long, value_name = "FILE")]
-    output: PathBuf,
-
-    /// The block size in bytes.
-    #[arg(short = 'b', long, default_value = "4096")]
-    block_size: usize,
-}
+/// Number of in-flight buffers handed between the reader and writer threads.
+const DEFAULT_BUFFER_COUNT: usize = 4;
 
 fn main() -> io::Result<()> {
-    let args = Args::parse();
-
-    // Open the input and output files.
-    let mut input_file = File::open(&args.input)?;
-
-    let mut output_file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .open(&args.output)?;
-
-    // Get the total size of the input file to create a properly sized progress bar.
-    let total_size = input_file.metadata()?.len();
-
-    // Create a new progress bar.
-    let pb = ProgressBar::new(total_size);
-    pb.set_style(
-        ProgressStyle::with_template(
-            "[{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} {msg}",
-        )
-        .unwrap()
-        .progress_chars("##-"),
-    );
-
-    // Create a buffer for copying data in blocks.
-    let mut buffer = vec![0; args.block_size];
-
-    loop {
-        let mut bytes_read = 0;
-        while bytes_read < args.block_size {
-            let n = match input_file.read(&mut buffer[bytes_read..]) {
-                Ok(0) => break, // Reached end of file
-                Ok(b_read) => b_read,
-                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue, // Try read again
-                Err(e) => {
-                    eprintln!("Error reading from input file: {}", e);
+    let cli = Cli::parse();
+    let output_path = cli.output.first();
+
+    validate_special_device_combo(cli.input.as_ref(), output_path)?;
+
+    let mut input = open_input_file(cli.input.as_ref(), cli.decompress.map(Into::into))?;
+    skip_input(&mut input, cli.skip * cli.block_size as u64, cli.block_size)?;
+
+    let force_output_codec = cli.compress.map(Into::into);
+    let seek_bytes = cli.seek * cli.block_size as u64;
+    let max_bytes = cli.count.map(|blocks| blocks * cli.block_size as u64);
+    let hash_algorithms: Vec<HashAlgorithm> = cli.hash.iter().map(|&algo| algo.into()).collect();
+
+    let config = ThreadedCopyConfig {
+        buffer_size: cli.block_size,
+        buffer_count: DEFAULT_BUFFER_COUNT,
+        sparse: cli.conv == Some(Conv::Sparse),
+        max_bytes,
+        hash_algorithms,
+    };
+
+    if cli.output.len() > 1 {
+        let outputs = cli
+            .output
+            .iter()
+            .map(|path| {
+                let mut output = open_output_file(Some(path), force_output_codec)?;
+                seek_output(&mut output, seek_bytes, cli.block_size)?;
+                Ok((path.display().to_string(), output))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        // `get_progress_target` below only runs the mount-safety check against
+        // `outputs[0]`; every other `--of` target needs the same check before
+        // we start writing to it.
+        for path in cli.output.iter().skip(1) {
+            check_and_handle_mount(path, true)?;
+        }
+
+        let (single_size, progress_type) = get_progress_target(&input, &outputs[0].1, output_path)?;
+        // Each of the N outputs receives the full input, so the bar's total
+        // must be N times one output's worth, not a single output's worth.
+        let total_size = single_size.map(|size| size * outputs.len() as u64);
+        let pb = create_progress_bar(total_size, progress_type.clone());
+
+        let report = copy_with_callback_multi(input, outputs, config, |bytes| pb.inc(bytes))?;
+        finish_pb_with_message(pb, progress_type);
+
+        print_digests(&report.digests);
+        report_targets(&report.targets);
+
+        if let Some(target) = &cli.verify {
+            let algo = verify_algorithm(&cli.hash);
+            verify_targets(
+                &cli.output,
+                &report.targets,
+                algo,
+                max_bytes,
+                seek_bytes,
+                force_output_codec,
+                target,
+            )?;
+        }
+    } else {
+        let mut output = open_output_file(output_path, force_output_codec)?;
+        seek_output(&mut output, seek_bytes, cli.block_size)?;
+
+        let (total_size, progress_type) = get_progress_target(&input, &output, output_path)?;
+        let pb = create_progress_bar(total_size, progress_type.clone());
+
+        let digests = copy_with_callback(input, output, config, |bytes| pb.inc(bytes))?;
+        finish_pb_with_message(pb, progress_type);
+
+        print_digests(&digests);
+
+        if let Some(target) = &cli.verify {
+            match output_path {
+                Some(path) => {
+                    let algo = verify_algorithm(&cli.hash);
+                    let computed =
+                        verify_written_digest(path, algo, max_bytes, seek_bytes, force_output_codec)?;
+                    if check_verify_target(&computed, target)? {
+                        println!("verify: OK ({computed})");
+                    } else {
+                        eprintln!("verify: MISMATCH (got {computed})");
+                        process::exit(1);
+                    }
+                }
+                None => {
+                    eprintln!("--verify requires a file output (--of)");
                     process::exit(1);
                 }
-            };
-            bytes_read += n;
+            }
         }
+    }
 
-        if bytes_read == 0 {
-            break; // End of file
+    Ok(())
+}
+
+/// The digest algorithm to verify with when `--verify` is given: the first
+/// `--hash` algorithm, or SHA-256 if none was requested.
+fn verify_algorithm(hash: &[HashAlgo]) -> HashAlgorithm {
+    hash.first()
+        .copied()
+        .map(Into::into)
+        .unwrap_or(HashAlgorithm::Sha256)
+}
+
+fn print_digests(digests: &[DigestResult]) {
+    for digest in digests {
+        println!("{}: {}", digest.algorithm, digest.hex);
+    }
+}
+
+/// Prints a per-target success/failure summary for a multi-output fan-out and
+/// exits with a nonzero status if any target failed.
+fn report_targets(targets: &[TargetOutcome]) {
+    let mut any_failed = false;
+    for target in targets {
+        match &target.result {
+            Ok(()) => println!("{}: OK", target.label),
+            Err(e) => {
+                any_failed = true;
+                eprintln!("{}: FAILED ({e})", target.label);
+            }
+        }
+    }
+
+    if any_failed {
+        process::exit(1);
+    }
+}
+
+/// Re-reads every successfully-written target and confirms its digest
+/// matches `target` (`--verify`), exiting nonzero if any mismatches.
+fn verify_targets(
+    paths: &[PathBuf],
+    outcomes: &[TargetOutcome],
+    algo: HashAlgorithm,
+    max_bytes: Option<u64>,
+    seek_bytes: u64,
+    force_codec: Option<Compression>,
+    target: &str,
+) -> io::Result<()> {
+    let mut any_mismatch = false;
+
+    for (path, outcome) in paths.iter().zip(outcomes) {
+        if !outcome.succeeded() {
+            continue;
+        }
+
+        match verify_written_digest(path, algo, max_bytes, seek_bytes, force_codec) {
+            Ok(computed) if check_verify_target(&computed, target)? => {
+                println!("{}: verify OK ({computed})", outcome.label);
+            }
+            Ok(computed) => {
+                any_mismatch = true;
+                eprintln!("{}: verify MISMATCH (got {computed})", outcome.label);
+            }
+            Err(e) => {
+                any_mismatch = true;
+                eprintln!("{}: verify error ({e})", outcome.label);
+            }
         }
+    }
 
-        output_file.write_all(&buffer[..bytes_read])?;
-        output_file.sync_data()?;
-        pb.inc(bytes_read as u64);
+    if any_mismatch {
+        process::exit(1);
     }
 
-    pb.finish_with_message("Copy complete!");
-    println!();
     Ok(())
 }
@@ -3,12 +3,12 @@ use indicatif::{ProgressBar, ProgressStyle};
 use rand::{RngCore, rng};
 use std::{
     fs::{File, OpenOptions},
-    io::{self, Read, Write, stdin, stdout},
+    io::{self, Read, Seek, SeekFrom, Write, stdin, stdout},
     path::PathBuf,
     process,
     sync::{
-        Arc,
-        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
     },
     thread,
     time::Duration,
@@ -25,6 +25,15 @@ pub enum InputSource {
     DevZero,
     DevNull,
     DevUrandom,
+    /// A file transparently decompressed as it's read, e.g. a `.zst` image.
+    Compressed(Box<dyn Read + Send>),
+    /// An Android sparse image (`SPARSE_HEADER_MAGIC`) expanded into a flat
+    /// stream as it's read. `expanded_size` is the full image size once
+    /// expanded, for progress reporting.
+    AndroidSparse {
+        reader: Box<dyn Read + Send>,
+        expanded_size: u64,
+    },
 }
 
 impl Read for InputSource {
@@ -46,6 +55,25 @@ impl Read for InputSource {
                 rng().fill_bytes(buf);
                 Ok(buf.len())
             }
+            InputSource::Compressed(reader) => reader.read(buf),
+            InputSource::AndroidSparse { reader, .. } => reader.read(buf),
+        }
+    }
+}
+
+impl Seek for InputSource {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            InputSource::File(file) => file.seek(pos),
+            InputSource::Stdin(_)
+            | InputSource::DevZero
+            | InputSource::DevNull
+            | InputSource::DevUrandom
+            | InputSource::Compressed(_)
+            | InputSource::AndroidSparse { .. } => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "input does not support seeking",
+            )),
         }
     }
 }
@@ -56,6 +84,8 @@ pub enum OutputSource {
     Stdout(io::Stdout),
     DevNull,
     DevFull,
+    /// A file transparently compressed as it's written, e.g. a `.zst` image.
+    Compressed(Box<dyn Write + Send>),
 }
 
 impl Write for OutputSource {
@@ -74,6 +104,7 @@ impl Write for OutputSource {
                     "No space left on device",
                 ))
             }
+            OutputSource::Compressed(writer) => writer.write(buf),
         }
     }
 
@@ -82,6 +113,22 @@ impl Write for OutputSource {
             OutputSource::File(file) => file.flush(),
             OutputSource::Stdout(stdout) => stdout.flush(),
             OutputSource::DevNull | OutputSource::DevFull => Ok(()), // Nothing to flush
+            OutputSource::Compressed(writer) => writer.flush(),
+        }
+    }
+}
+
+impl Seek for OutputSource {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            OutputSource::File(file) => file.seek(pos),
+            OutputSource::Stdout(_)
+            | OutputSource::DevNull
+            | OutputSource::DevFull
+            | OutputSource::Compressed(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "output does not support seeking",
+            )),
         }
     }
 }
@@ -119,8 +166,277 @@ pub fn validate_special_device_combo(
     Ok(())
 }
 
-/// Opens an input file for reading
-pub fn open_input_file(path: Option<&PathBuf>) -> io::Result<InputSource> {
+/// Compression codecs auto-detected from common disc-image extensions, or
+/// requested explicitly via `--compress`/`--decompress`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Compression {
+    Zstd,
+    Xz,
+    Bzip2,
+    Gzip,
+}
+
+impl Compression {
+    /// Sniffs the codec from a file's extension (`.zst`, `.xz`, `.bz2`, `.gz`).
+    fn from_extension(path: &PathBuf) -> Option<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("zst") => Some(Compression::Zstd),
+            Some("xz") => Some(Compression::Xz),
+            Some("bz2") => Some(Compression::Bzip2),
+            Some("gz") => Some(Compression::Gzip),
+            _ => None,
+        }
+    }
+
+    fn wrap_reader(self, file: File) -> io::Result<Box<dyn Read + Send>> {
+        Ok(match self {
+            Compression::Zstd => Box::new(zstd::Decoder::new(file)?),
+            Compression::Xz => Box::new(xz2::read::XzDecoder::new(file)),
+            Compression::Bzip2 => Box::new(bzip2::read::BzDecoder::new(file)),
+            Compression::Gzip => Box::new(flate2::read::GzDecoder::new(file)),
+        })
+    }
+
+    fn wrap_writer(self, file: File) -> io::Result<Box<dyn Write + Send>> {
+        Ok(match self {
+            Compression::Zstd => Box::new(zstd::Encoder::new(file, 0)?.auto_finish()),
+            Compression::Xz => Box::new(xz2::write::XzEncoder::new(file, 6)),
+            Compression::Bzip2 => Box::new(bzip2::write::BzEncoder::new(
+                file,
+                bzip2::Compression::default(),
+            )),
+            Compression::Gzip => Box::new(flate2::write::GzEncoder::new(
+                file,
+                flate2::Compression::default(),
+            )),
+        })
+    }
+}
+
+/// Magic number identifying an Android sparse image (`SPARSE_HEADER_MAGIC`).
+const ANDROID_SPARSE_MAGIC: u32 = 0xED26FF3A;
+
+const ANDROID_CHUNK_TYPE_RAW: u16 = 0xCAC1;
+const ANDROID_CHUNK_TYPE_FILL: u16 = 0xCAC2;
+const ANDROID_CHUNK_TYPE_DONT_CARE: u16 = 0xCAC3;
+const ANDROID_CHUNK_TYPE_CRC32: u16 = 0xCAC4;
+
+struct AndroidSparseFileHeader {
+    file_hdr_sz: u16,
+    chunk_hdr_sz: u16,
+    blk_sz: u32,
+    total_blks: u32,
+    total_chunks: u32,
+}
+
+enum AndroidSparseChunk {
+    Raw {
+        remaining: u64,
+    },
+    Fill {
+        word: [u8; 4],
+        offset: usize,
+        remaining: u64,
+    },
+    DontCare {
+        remaining: u64,
+    },
+}
+
+impl AndroidSparseChunk {
+    fn remaining(&self) -> u64 {
+        match self {
+            AndroidSparseChunk::Raw { remaining }
+            | AndroidSparseChunk::Fill { remaining, .. }
+            | AndroidSparseChunk::DontCare { remaining } => *remaining,
+        }
+    }
+}
+
+/// Expands an Android sparse image into a flat byte stream as it's read:
+/// `RAW` chunks are copied through, `FILL` chunks repeat a 4-byte word,
+/// `DONT_CARE` chunks become zeroes, and `CRC32` chunks (which carry no
+/// output payload) are skipped.
+struct AndroidSparseReader {
+    file: File,
+    header: AndroidSparseFileHeader,
+    chunks_left: u32,
+    chunk: Option<AndroidSparseChunk>,
+}
+
+impl AndroidSparseReader {
+    /// Parses the 28-byte file header, assuming the magic has already been
+    /// sniffed and the file position is at the very start.
+    fn new(mut file: File) -> io::Result<Self> {
+        let mut raw = [0u8; 28];
+        file.read_exact(&mut raw)?;
+
+        if u32::from_le_bytes(raw[0..4].try_into().unwrap()) != ANDROID_SPARSE_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not an Android sparse image",
+            ));
+        }
+
+        let header = AndroidSparseFileHeader {
+            file_hdr_sz: u16::from_le_bytes(raw[8..10].try_into().unwrap()),
+            chunk_hdr_sz: u16::from_le_bytes(raw[10..12].try_into().unwrap()),
+            blk_sz: u32::from_le_bytes(raw[12..16].try_into().unwrap()),
+            total_blks: u32::from_le_bytes(raw[16..20].try_into().unwrap()),
+            total_chunks: u32::from_le_bytes(raw[20..24].try_into().unwrap()),
+        };
+
+        // A newer minor version may have extended the header; skip whatever
+        // we haven't already accounted for.
+        skip_exact(
+            &mut file,
+            (header.file_hdr_sz as u64).saturating_sub(raw.len() as u64),
+        )?;
+
+        Ok(Self {
+            file,
+            chunks_left: header.total_chunks,
+            header,
+            chunk: None,
+        })
+    }
+
+    fn expanded_size(&self) -> u64 {
+        self.header.total_blks as u64 * self.header.blk_sz as u64
+    }
+
+    /// Reads the next chunk header and sets `self.chunk`, skipping CRC32
+    /// chunks (which have no output payload) transparently. Returns `false`
+    /// once every chunk has been consumed.
+    fn advance(&mut self) -> io::Result<bool> {
+        loop {
+            if self.chunks_left == 0 {
+                return Ok(false);
+            }
+            self.chunks_left -= 1;
+
+            let mut raw = [0u8; 12];
+            self.file.read_exact(&mut raw)?;
+            let chunk_type = u16::from_le_bytes(raw[0..2].try_into().unwrap());
+            let chunk_sz = u32::from_le_bytes(raw[4..8].try_into().unwrap());
+            skip_exact(
+                &mut self.file,
+                (self.header.chunk_hdr_sz as u64).saturating_sub(raw.len() as u64),
+            )?;
+
+            let payload = chunk_sz as u64 * self.header.blk_sz as u64;
+
+            self.chunk = Some(match chunk_type {
+                ANDROID_CHUNK_TYPE_RAW => AndroidSparseChunk::Raw { remaining: payload },
+                ANDROID_CHUNK_TYPE_FILL => {
+                    let mut word = [0u8; 4];
+                    self.file.read_exact(&mut word)?;
+                    AndroidSparseChunk::Fill {
+                        word,
+                        offset: 0,
+                        remaining: payload,
+                    }
+                }
+                ANDROID_CHUNK_TYPE_DONT_CARE => AndroidSparseChunk::DontCare { remaining: payload },
+                ANDROID_CHUNK_TYPE_CRC32 => {
+                    skip_exact(&mut self.file, 4)?;
+                    continue;
+                }
+                other => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("unknown Android sparse chunk type {other:#06x}"),
+                    ));
+                }
+            });
+
+            return Ok(true);
+        }
+    }
+}
+
+impl Read for AndroidSparseReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let has_data = matches!(&self.chunk, Some(chunk) if chunk.remaining() > 0);
+            if !has_data {
+                if !self.advance()? {
+                    return Ok(0);
+                }
+                continue;
+            }
+
+            return match self.chunk.as_mut().unwrap() {
+                AndroidSparseChunk::Raw { remaining } => {
+                    let want = buf.len().min(*remaining as usize);
+                    let n = self.file.read(&mut buf[..want])?;
+                    if n == 0 {
+                        return Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "truncated Android sparse RAW chunk",
+                        ));
+                    }
+                    *remaining -= n as u64;
+                    Ok(n)
+                }
+                AndroidSparseChunk::Fill {
+                    word,
+                    offset,
+                    remaining,
+                } => {
+                    let want = buf.len().min(*remaining as usize);
+                    for (i, b) in buf[..want].iter_mut().enumerate() {
+                        *b = word[(*offset + i) % word.len()];
+                    }
+                    *offset = (*offset + want) % word.len();
+                    *remaining -= want as u64;
+                    Ok(want)
+                }
+                AndroidSparseChunk::DontCare { remaining } => {
+                    let want = buf.len().min(*remaining as usize);
+                    buf[..want].fill(0);
+                    *remaining -= want as u64;
+                    Ok(want)
+                }
+            };
+        }
+    }
+}
+
+/// Reads and discards exactly `bytes` bytes, used to skip over header
+/// padding that a newer sparse-format minor version may have added.
+fn skip_exact(reader: &mut impl Read, bytes: u64) -> io::Result<()> {
+    if bytes == 0 {
+        return Ok(());
+    }
+    let skipped = io::copy(&mut reader.take(bytes), &mut io::sink())?;
+    if skipped < bytes {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "unexpected end of file while skipping",
+        ));
+    }
+    Ok(())
+}
+
+/// Sniffs whether `file` starts with the Android sparse magic, restoring the
+/// file position to where it found it (the start of the file) either way.
+fn is_android_sparse(file: &mut File) -> io::Result<bool> {
+    let mut magic = [0u8; 4];
+    let pos = file.stream_position()?;
+    let read_full = file.read_exact(&mut magic).is_ok();
+    file.seek(SeekFrom::Start(pos))?;
+    Ok(read_full && u32::from_le_bytes(magic) == ANDROID_SPARSE_MAGIC)
+}
+
+/// Opens an input file for reading. `force_codec` overrides extension
+/// sniffing (`--decompress`); pass `None` to rely on the extension alone.
+/// Android sparse images are detected from their magic number regardless of
+/// `force_codec` and are expanded transparently.
+pub fn open_input_file(
+    path: Option<&PathBuf>,
+    force_codec: Option<Compression>,
+) -> io::Result<InputSource> {
     match path {
         Some(path) => {
             let path_str = path.to_string_lossy();
@@ -128,30 +444,111 @@ pub fn open_input_file(path: Option<&PathBuf>) -> io::Result<InputSource> {
                 "/dev/null" => Ok(InputSource::DevNull),
                 "/dev/zero" => Ok(InputSource::DevZero),
                 "/dev/urandom" | "/dev/random" => Ok(InputSource::DevUrandom),
-                _ => Ok(InputSource::File(File::open(path)?)),
+                _ => {
+                    let mut file = File::open(path)?;
+                    if is_android_sparse(&mut file)? {
+                        let reader = AndroidSparseReader::new(file)?;
+                        let expanded_size = reader.expanded_size();
+                        return Ok(InputSource::AndroidSparse {
+                            reader: Box::new(reader),
+                            expanded_size,
+                        });
+                    }
+
+                    match force_codec.or_else(|| Compression::from_extension(path)) {
+                        Some(codec) => Ok(InputSource::Compressed(codec.wrap_reader(file)?)),
+                        None => Ok(InputSource::File(file)),
+                    }
+                }
             }
         }
         None => Ok(InputSource::Stdin(stdin())),
     }
 }
 
-/// Opens an output file for writing
-pub fn open_output_file(path: Option<&PathBuf>) -> io::Result<OutputSource> {
+/// Opens an output file for writing. `force_codec` overrides extension
+/// sniffing (`--compress`); pass `None` to rely on the extension alone.
+pub fn open_output_file(
+    path: Option<&PathBuf>,
+    force_codec: Option<Compression>,
+) -> io::Result<OutputSource> {
     match path {
         Some(path) => {
             let path_str = path.to_string_lossy();
             match path_str.as_ref() {
                 "/dev/null" => Ok(OutputSource::DevNull),
                 "/dev/full" => Ok(OutputSource::DevFull),
-                _ => Ok(OutputSource::File(
-                    OpenOptions::new().write(true).create(true).open(path)?,
-                )),
+                _ => {
+                    let file = OpenOptions::new().write(true).create(true).open(path)?;
+                    match force_codec.or_else(|| Compression::from_extension(path)) {
+                        Some(codec) => Ok(OutputSource::Compressed(codec.wrap_writer(file)?)),
+                        None => Ok(OutputSource::File(file)),
+                    }
+                }
             }
         }
         None => Ok(OutputSource::Stdout(stdout())),
     }
 }
 
+/// Advances past `bytes` bytes of `input` before the real copy starts
+/// (`skip=N`). Seekable inputs just seek; non-seekable ones (stdin,
+/// `/dev/zero`, `/dev/urandom`, compressed streams) are read and discarded
+/// `scratch_size` bytes at a time.
+pub fn skip_input(input: &mut InputSource, bytes: u64, scratch_size: usize) -> io::Result<()> {
+    if bytes == 0 {
+        return Ok(());
+    }
+
+    if let InputSource::File(file) = input {
+        file.seek(SeekFrom::Start(bytes))?;
+        return Ok(());
+    }
+
+    discard_bytes(input, bytes, scratch_size)
+}
+
+/// Advances past `bytes` bytes of `output` before the real copy starts
+/// (`seek=N`). A file-backed output just seeks; other outputs can't be
+/// sought, so the gap is emulated by writing zero bytes.
+pub fn seek_output(output: &mut OutputSource, bytes: u64, scratch_size: usize) -> io::Result<()> {
+    if bytes == 0 {
+        return Ok(());
+    }
+
+    if let OutputSource::File(_) = output {
+        output.seek(SeekFrom::Start(bytes))?;
+        return Ok(());
+    }
+
+    let zeros = vec![0u8; scratch_size.max(1)];
+    let mut remaining = bytes;
+    while remaining > 0 {
+        let chunk = (zeros.len() as u64).min(remaining) as usize;
+        output.write_all(&zeros[..chunk])?;
+        remaining -= chunk as u64;
+    }
+    Ok(())
+}
+
+/// Reads and discards `bytes` bytes from `reader`, stopping early on EOF.
+fn discard_bytes<R: Read + ?Sized>(
+    reader: &mut R,
+    mut bytes: u64,
+    scratch_size: usize,
+) -> io::Result<()> {
+    let mut scratch = vec![0u8; scratch_size.max(1)];
+    while bytes > 0 {
+        let chunk = (scratch.len() as u64).min(bytes) as usize;
+        let n = reader.read(&mut scratch[..chunk])?;
+        if n == 0 {
+            break; // Hit EOF before we finished skipping.
+        }
+        bytes -= n as u64;
+    }
+    Ok(())
+}
+
 fn get_available_space(path: &PathBuf) -> io::Result<Option<u64>> {
     let disks = Disks::new_with_refreshed_list();
 
@@ -316,7 +713,10 @@ fn is_system_drive(path: &PathBuf) -> bool {
     false
 }
 
-fn check_and_handle_mount(path: &PathBuf, is_output: bool) -> io::Result<()> {
+/// Unmounts `path` if it's currently mounted, so writing to it (e.g. via
+/// `--of`) doesn't corrupt a live filesystem. A no-op for input checks
+/// (`is_output = false`) and for paths on the system drive.
+pub fn check_and_handle_mount(path: &PathBuf, is_output: bool) -> io::Result<()> {
     if !is_output {
         return Ok(());
     }
@@ -414,6 +814,18 @@ pub fn get_progress_target(
             // /dev/null input - immediate EOF
             Ok((Some(0), ProgressType::FileTransfer))
         }
+        InputSource::Compressed(_) => {
+            // Compressed input - the on-disk size isn't the transferred size
+            Ok((None, ProgressType::StreamTransfer))
+        }
+        InputSource::AndroidSparse { expanded_size, .. } => {
+            if let Some(path) = output_path {
+                check_and_handle_mount(path, true)?;
+            }
+
+            // Report the expanded size, not the compact on-disk size.
+            Ok((Some(*expanded_size), ProgressType::FileTransfer))
+        }
         InputSource::DevZero => {
             if let Some(path) = output_path {
                 check_and_handle_mount(path, true)?;
@@ -428,7 +840,7 @@ pub fn get_progress_target(
                         Ok((None, ProgressType::FillWithZeros))
                     }
                 }
-                OutputSource::Stdout(_) | OutputSource::DevNull => {
+                OutputSource::Stdout(_) | OutputSource::DevNull | OutputSource::Compressed(_) => {
                     Ok((None, ProgressType::FillWithZeros))
                 } // Infinite Capacity
                 OutputSource::DevFull => Ok((Some(0), ProgressType::FillWithZeros)), // No capacity
@@ -448,7 +860,7 @@ pub fn get_progress_target(
                         Ok((None, ProgressType::FillWithRandom))
                     }
                 }
-                OutputSource::Stdout(_) | OutputSource::DevNull => {
+                OutputSource::Stdout(_) | OutputSource::DevNull | OutputSource::Compressed(_) => {
                     Ok((None, ProgressType::FillWithRandom))
                 } // Infinite capacity
                 OutputSource::DevFull => Ok((Some(0), ProgressType::FillWithRandom)), // No capacity
@@ -542,6 +954,238 @@ impl DataBuffer {
 pub struct ThreadedCopyConfig {
     pub buffer_size: usize,
     pub buffer_count: usize,
+    /// When set, runs of zero bytes at least `buffer_size` long are skipped
+    /// via `seek` instead of being written, producing a sparse output file
+    /// (`conv=sparse`). Only has an effect when the output is a file.
+    pub sparse: bool,
+    /// Caps the copy at this many input bytes (`count=N` blocks, converted
+    /// to bytes by the caller). `None` copies the whole input.
+    pub max_bytes: Option<u64>,
+    /// Digest algorithms to compute over the input stream in one pass
+    /// (`--hash`). Empty means no hashing.
+    pub hash_algorithms: Vec<HashAlgorithm>,
+}
+
+/// Digest algorithms supported by `--hash`/`--verify`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Crc32,
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl HashAlgorithm {
+    fn build(self) -> Box<dyn DigestHasher> {
+        match self {
+            HashAlgorithm::Crc32 => Box::new(Crc32Digest(crc32fast::Hasher::new())),
+            HashAlgorithm::Md5 => Box::new(GenericDigest {
+                inner: md5::Md5::default(),
+                name: "md5",
+            }),
+            HashAlgorithm::Sha1 => Box::new(GenericDigest {
+                inner: sha1::Sha1::default(),
+                name: "sha1",
+            }),
+            HashAlgorithm::Sha256 => Box::new(GenericDigest {
+                inner: sha2::Sha256::default(),
+                name: "sha256",
+            }),
+        }
+    }
+}
+
+/// One digest algorithm fed incrementally, used so `--hash crc32,sha256` can
+/// run every requested algorithm in a single pass over the data.
+trait DigestHasher: Send {
+    fn update(&mut self, data: &[u8]);
+    fn name(&self) -> &'static str;
+    fn finalize_hex(self: Box<Self>) -> String;
+}
+
+struct Crc32Digest(crc32fast::Hasher);
+
+impl DigestHasher for Crc32Digest {
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    fn name(&self) -> &'static str {
+        "crc32"
+    }
+
+    fn finalize_hex(self: Box<Self>) -> String {
+        format!("{:08x}", self.0.finalize())
+    }
+}
+
+struct GenericDigest<D> {
+    inner: D,
+    name: &'static str,
+}
+
+impl<D: digest::Digest + Send> DigestHasher for GenericDigest<D> {
+    fn update(&mut self, data: &[u8]) {
+        digest::Digest::update(&mut self.inner, data);
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn finalize_hex(self: Box<Self>) -> String {
+        digest::Digest::finalize(self.inner)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+}
+
+/// One finished `--hash` digest, as returned by `copy_with_callback`.
+#[derive(Debug, Clone)]
+pub struct DigestResult {
+    pub algorithm: &'static str,
+    pub hex: String,
+}
+
+/// Re-reads `path` (up to `expected_bytes`, when given) and digests it with
+/// `algorithm`, so the result can be compared against the digest produced
+/// while writing it (`--verify`). `seek_bytes` must match the `--seek` offset
+/// the copy wrote at, so the digested region lines up with what was actually
+/// written instead of the start of the file. `force_codec` must match the
+/// `--compress` codec the copy used, if any; the digest recorded during the
+/// copy is over the plaintext stream, so the on-disk compressed bytes are
+/// decompressed the same way `open_input_file` does before hashing.
+pub fn verify_written_digest(
+    path: &PathBuf,
+    algorithm: HashAlgorithm,
+    expected_bytes: Option<u64>,
+    seek_bytes: u64,
+    force_codec: Option<Compression>,
+) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let codec = force_codec.or_else(|| Compression::from_extension(path));
+
+    // A compressed output's on-disk bytes never match the plaintext digest
+    // computed during the copy, so decompress before hashing the same way
+    // open_input_file does. `--seek`'s leading zero bytes were written
+    // *before* compression, so they have to be discarded post-decompression
+    // too, not skipped via a raw file seek.
+    let mut reader: Box<dyn Read + Send> = match codec {
+        Some(codec) => {
+            let mut decoded = codec.wrap_reader(file)?;
+            if seek_bytes > 0 {
+                discard_bytes(&mut decoded, seek_bytes, 1 << 16)?;
+            }
+            decoded
+        }
+        None => {
+            if seek_bytes > 0 {
+                file.seek(SeekFrom::Start(seek_bytes))?;
+            }
+            Box::new(file)
+        }
+    };
+
+    let mut hasher = algorithm.build();
+    let mut buf = vec![0u8; 1 << 20];
+    let mut remaining = expected_bytes;
+
+    loop {
+        let want = match remaining {
+            Some(0) => break,
+            Some(r) => buf.len().min(r as usize),
+            None => buf.len(),
+        };
+
+        let n = reader.read(&mut buf[..want])?;
+        if n == 0 {
+            break;
+        }
+
+        hasher.update(&buf[..n]);
+        if let Some(r) = remaining.as_mut() {
+            *r -= n as u64;
+        }
+    }
+
+    Ok(hasher.finalize_hex())
+}
+
+/// Checks a computed digest against the `--verify` target, which is either a
+/// bare hex digest or the path to a file containing one (as produced by
+/// tools like `sha256sum`).
+pub fn check_verify_target(computed_hex: &str, target: &str) -> io::Result<bool> {
+    let expected = if PathBuf::from(target).is_file() {
+        std::fs::read_to_string(target)?
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_lowercase()
+    } else {
+        target.to_lowercase()
+    };
+
+    Ok(expected == computed_hex.to_lowercase())
+}
+
+/// Writes `buf` to `output`, seeking over runs of zero bytes at least
+/// `threshold` bytes long instead of writing them. Short zero runs are
+/// written out normally since punching a hole for them isn't worthwhile.
+///
+/// Seeking past the end of a file doesn't allocate space for the gap, so the
+/// caller must `set_len` the file to the correct total size once the whole
+/// copy is done in case it ends in a hole.
+fn write_sparse(output: &mut OutputSource, buf: &[u8], threshold: usize) -> io::Result<()> {
+    let mut i = 0;
+    while i < buf.len() {
+        let run_start = i;
+        let is_zero_run = buf[i] == 0;
+        while i < buf.len() && (buf[i] == 0) == is_zero_run {
+            i += 1;
+        }
+        let run = &buf[run_start..i];
+
+        if is_zero_run && run.len() >= threshold {
+            let hole_start = output.stream_position()?;
+            output.seek(SeekFrom::Current(run.len() as i64))?;
+            if let OutputSource::File(file) = output {
+                let _ = punch_hole(file, hole_start, run.len() as u64);
+            }
+        } else {
+            output.write_all(run)?;
+        }
+    }
+    Ok(())
+}
+
+/// Deallocates the disk blocks backing `[offset, offset + len)` in `file`
+/// without changing its length, turning an already-written zero run into a
+/// real hole. Best-effort: filesystems that don't support it simply keep the
+/// zeroes on disk, which is still correct, just not sparse.
+#[cfg(target_os = "linux")]
+fn punch_hole(file: &File, offset: u64, len: u64) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let ret = unsafe {
+        libc::fallocate(
+            file.as_raw_fd(),
+            libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE,
+            offset as libc::off_t,
+            len as libc::off_t,
+        )
+    };
+
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn punch_hole(_file: &File, _offset: u64, _len: u64) -> io::Result<()> {
+    Ok(())
 }
 
 pub fn copy_with_callback<F>(
@@ -549,12 +1193,18 @@ pub fn copy_with_callback<F>(
     mut output: OutputSource,
     config: ThreadedCopyConfig,
     callback: F,
-) -> io::Result<()>
+) -> io::Result<Vec<DigestResult>>
 where
     F: Fn(u64) + Send + Sync,
 {
     let bytes_processed = Arc::new(AtomicU64::new(0));
     let result = Arc::new(std::sync::Mutex::new(Ok(())));
+    let digests: Mutex<Vec<DigestResult>> = Mutex::new(Vec::new());
+    // The reader's final EOF-detecting recv consumes a buffer it never
+    // returns to the pool, so `empty_rx`/`full_rx` never both drain to
+    // empty once `buffer_count > 1` — the progress thread must watch this
+    // flag instead of polling channel emptiness, or it blocks forever.
+    let writer_done = Arc::new(std::sync::atomic::AtomicBool::new(false));
 
     let (empty_tx, empty_rx) = channel::bounded(config.buffer_count);
     let (full_tx, full_rx) = channel::bounded(config.buffer_count);
@@ -563,22 +1213,48 @@ where
         empty_tx.send(DataBuffer::new(config.buffer_size)).unwrap();
     }
 
+    // conv=sparse only has an effect on file-backed outputs (stdout, /dev/null,
+    // etc. don't support seeking, so sparse detection is meaningless for them).
+    let sparse = config.sparse && matches!(output, OutputSource::File(_));
+
+    // `seek_output` may have already advanced the output past its start
+    // before this copy began (`--seek`); `set_len` below must preserve that
+    // region rather than truncating back to just the bytes this call wrote.
+    let sparse_start_offset = if sparse { output.stream_position()? } else { 0 };
+
     thread::scope(|scope| {
         scope.spawn(|| {
+            let mut hashers: Vec<Box<dyn DigestHasher>> =
+                config.hash_algorithms.iter().map(|a| a.build()).collect();
+
             let read_result = (|| -> io::Result<()> {
+                let mut total_read = 0u64;
                 loop {
                     let mut buffer = match empty_rx.recv() {
                         Ok(buf) => buf,
                         Err(_) => break,
                     };
 
-                    match input.read(&mut buffer.data) {
+                    let want = match config.max_bytes {
+                        Some(limit) if total_read >= limit => {
+                            drop(full_tx);
+                            break;
+                        }
+                        Some(limit) => buffer.data.len().min((limit - total_read) as usize),
+                        None => buffer.data.len(),
+                    };
+
+                    match input.read(&mut buffer.data[..want]) {
                         Ok(0) => {
                             drop(full_tx);
                             break;
                         }
                         Ok(bytes_read) => {
                             buffer.bytes_used = bytes_read;
+                            total_read += bytes_read as u64;
+                            for hasher in &mut hashers {
+                                hasher.update(&buffer.data[..bytes_read]);
+                            }
                             if full_tx.send(buffer).is_err() {
                                 break;
                             }
@@ -589,6 +1265,17 @@ where
                 Ok(())
             })();
 
+            *digests.lock().unwrap() = hashers
+                .into_iter()
+                .map(|h| {
+                    let algorithm = h.name();
+                    DigestResult {
+                        algorithm,
+                        hex: h.finalize_hex(),
+                    }
+                })
+                .collect();
+
             if let Err(e) = read_result {
                 *result.lock().unwrap() = Err(io::Error::new(
                     io::ErrorKind::Other,
@@ -605,7 +1292,11 @@ where
                         Err(_) => break,
                     };
 
-                    output.write_all(buffer.as_slice())?;
+                    if sparse {
+                        write_sparse(&mut output, buffer.as_slice(), config.buffer_size)?;
+                    } else {
+                        output.write_all(buffer.as_slice())?;
+                    }
                     output.flush()?;
 
                     bytes_processed.fetch_add(buffer.bytes_used as u64, Ordering::Relaxed);
@@ -623,6 +1314,8 @@ where
                     format!("Write error: {e}"),
                 ));
             }
+
+            writer_done.store(true, Ordering::Release);
         });
 
         scope.spawn(|| {
@@ -638,7 +1331,7 @@ where
                     last_bytes = current_bytes;
                 }
 
-                if empty_rx.is_empty() && full_rx.is_empty() {
+                if writer_done.load(Ordering::Acquire) {
                     let final_bytes = bytes_processed.load(Ordering::Relaxed);
                     let final_delta = final_bytes - last_bytes;
                     if final_delta > 0 {
@@ -656,7 +1349,15 @@ where
         .as_ref()
         .map_err(|e| io::Error::new(e.kind(), e.to_string()))?;
 
-    Ok(())
+    // A trailing zero run is represented only by the writer thread's final
+    // seek, so the file is left short unless we grow it back out explicitly.
+    if sparse {
+        if let OutputSource::File(file) = &output {
+            file.set_len(sparse_start_offset + bytes_processed.load(Ordering::Relaxed))?;
+        }
+    }
+
+    Ok(digests.into_inner().unwrap())
 }
 
 pub fn copy_with_progress(
@@ -668,4 +1369,598 @@ pub fn copy_with_progress(
     copy_with_callback(input, output, config, |bytes| {
         pb.inc(bytes);
     })
+    .map(|_digests| ())
+}
+
+/// A buffer shared by every output in a `copy_with_callback_multi` fan-out.
+/// `pending` starts at the number of outputs and is decremented by each
+/// writer once it has consumed the buffer; the writer that drives it to zero
+/// is responsible for recycling a fresh empty buffer back to the reader.
+struct SharedBuffer {
+    buffer: DataBuffer,
+    pending: AtomicUsize,
+}
+
+/// The result of copying to one target passed to `copy_with_callback_multi`.
+pub struct TargetOutcome {
+    /// Label identifying the target, typically its path.
+    pub label: String,
+    pub result: io::Result<()>,
+}
+
+impl TargetOutcome {
+    pub fn succeeded(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// The result of a `copy_with_callback_multi` fan-out: one outcome per
+/// target plus the digests computed over the (single) input stream.
+pub struct MultiCopyReport {
+    pub targets: Vec<TargetOutcome>,
+    pub digests: Vec<DigestResult>,
+}
+
+/// Fans a single input stream out to several outputs concurrently, giving
+/// each output its own writer thread so a slow or failing target doesn't
+/// stall the others. Every writer reads the exact same sequence of buffers;
+/// a buffer is only returned to the empty pool once every writer has
+/// consumed it.
+///
+/// A per-target write failure doesn't abort the copy: that target stops
+/// writing (while still draining its channel so it doesn't block the
+/// others) and its error is reported in the returned `TargetOutcome`. The
+/// function only returns `Err` if the input itself can't be read.
+pub fn copy_with_callback_multi<F>(
+    mut input: InputSource,
+    outputs: Vec<(String, OutputSource)>,
+    config: ThreadedCopyConfig,
+    callback: F,
+) -> io::Result<MultiCopyReport>
+where
+    F: Fn(u64) + Send + Sync,
+{
+    let num_outputs = outputs.len();
+    let labels: Vec<String> = outputs.iter().map(|(label, _)| label.clone()).collect();
+
+    let bytes_processed = Arc::new(AtomicU64::new(0));
+    let read_result = Arc::new(Mutex::new(Ok(())));
+    let digests: Mutex<Vec<DigestResult>> = Mutex::new(Vec::new());
+    let errors: Vec<Mutex<Option<io::Error>>> = (0..num_outputs).map(|_| Mutex::new(None)).collect();
+    // As in copy_with_callback, the reader's final EOF-detecting recv
+    // strands a buffer in `empty_rx` whenever `buffer_count > 1`, so the
+    // progress thread must count finished writers instead of polling
+    // channel emptiness, or it blocks forever.
+    let writers_done = Arc::new(AtomicUsize::new(0));
+
+    let (empty_tx, empty_rx) = channel::bounded(config.buffer_count);
+    for _ in 0..config.buffer_count {
+        empty_tx.send(DataBuffer::new(config.buffer_size)).unwrap();
+    }
+
+    let mut full_txs = Vec::with_capacity(num_outputs);
+    let mut full_rxs = Vec::with_capacity(num_outputs);
+    for _ in 0..num_outputs {
+        let (tx, rx) = channel::bounded::<Arc<SharedBuffer>>(config.buffer_count);
+        full_txs.push(tx);
+        full_rxs.push(rx);
+    }
+
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            let mut hashers: Vec<Box<dyn DigestHasher>> =
+                config.hash_algorithms.iter().map(|a| a.build()).collect();
+
+            let read_res = (|| -> io::Result<()> {
+                let mut total_read = 0u64;
+                loop {
+                    let mut buffer = match empty_rx.recv() {
+                        Ok(buf) => buf,
+                        Err(_) => break,
+                    };
+
+                    let want = match config.max_bytes {
+                        Some(limit) if total_read >= limit => {
+                            full_txs.clear();
+                            break;
+                        }
+                        Some(limit) => buffer.data.len().min((limit - total_read) as usize),
+                        None => buffer.data.len(),
+                    };
+
+                    match input.read(&mut buffer.data[..want]) {
+                        Ok(0) => {
+                            full_txs.clear(); // drop every sender, closing all writer channels
+                            break;
+                        }
+                        Ok(bytes_read) => {
+                            buffer.bytes_used = bytes_read;
+                            total_read += bytes_read as u64;
+                            for hasher in &mut hashers {
+                                hasher.update(&buffer.data[..bytes_read]);
+                            }
+                            let shared = Arc::new(SharedBuffer {
+                                buffer,
+                                pending: AtomicUsize::new(num_outputs),
+                            });
+                            for tx in &full_txs {
+                                // A writer that already exited just drops its copy.
+                                let _ = tx.send(Arc::clone(&shared));
+                            }
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+                Ok(())
+            })();
+
+            *digests.lock().unwrap() = hashers
+                .into_iter()
+                .map(|h| {
+                    let algorithm = h.name();
+                    DigestResult {
+                        algorithm,
+                        hex: h.finalize_hex(),
+                    }
+                })
+                .collect();
+
+            if let Err(e) = read_res {
+                *read_result.lock().unwrap() = Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("Read error: {e}"),
+                ));
+            }
+        });
+
+        for (idx, (_, mut output)) in outputs.into_iter().enumerate() {
+            let full_rx = full_rxs[idx].clone();
+            let empty_tx = empty_tx.clone();
+            let error_slot = &errors[idx];
+            let bytes_processed = &bytes_processed;
+            let config = &config;
+            let writers_done = &writers_done;
+            scope.spawn(move || {
+                let mut local_bytes = 0u64;
+
+                // conv=sparse only has an effect on file-backed outputs (see
+                // copy_with_callback); non-file targets just write_all below.
+                let sparse = config.sparse && matches!(output, OutputSource::File(_));
+
+                // `seek_output` may have already advanced this output past
+                // its start (`--seek`); `set_len` below must preserve that
+                // region rather than truncating back to just what this
+                // thread wrote.
+                let start_offset = if sparse {
+                    match output.stream_position() {
+                        Ok(pos) => pos,
+                        Err(e) => {
+                            *error_slot.lock().unwrap() = Some(e);
+                            0
+                        }
+                    }
+                } else {
+                    0
+                };
+
+                loop {
+                    let shared = match full_rx.recv() {
+                        Ok(buf) => buf,
+                        Err(_) => break,
+                    };
+
+                    if error_slot.lock().unwrap().is_none() {
+                        let write_res = (|| -> io::Result<()> {
+                            if sparse {
+                                write_sparse(&mut output, shared.buffer.as_slice(), config.buffer_size)?;
+                            } else {
+                                output.write_all(shared.buffer.as_slice())?;
+                            }
+                            output.flush()
+                        })();
+
+                        match write_res {
+                            Ok(()) => {
+                                local_bytes += shared.buffer.bytes_used as u64;
+                                bytes_processed
+                                    .fetch_add(shared.buffer.bytes_used as u64, Ordering::Relaxed);
+                            }
+                            Err(e) => *error_slot.lock().unwrap() = Some(e),
+                        }
+                    }
+
+                    if shared.pending.fetch_sub(1, Ordering::AcqRel) == 1 {
+                        let _ = empty_tx.send(DataBuffer::new(config.buffer_size));
+                    }
+                }
+
+                if sparse && error_slot.lock().unwrap().is_none() {
+                    if let OutputSource::File(file) = &output {
+                        let _ = file.set_len(start_offset + local_bytes);
+                    }
+                }
+
+                writers_done.fetch_add(1, Ordering::Release);
+            });
+        }
+
+        scope.spawn(|| {
+            let mut last_bytes = 0u64;
+            loop {
+                thread::sleep(Duration::from_millis(100));
+
+                let current_bytes = bytes_processed.load(Ordering::Relaxed);
+                let delta = current_bytes - last_bytes;
+
+                if delta > 0 {
+                    callback(delta);
+                    last_bytes = current_bytes;
+                }
+
+                if writers_done.load(Ordering::Acquire) == num_outputs {
+                    let final_bytes = bytes_processed.load(Ordering::Relaxed);
+                    let final_delta = final_bytes - last_bytes;
+                    if final_delta > 0 {
+                        callback(final_delta);
+                    }
+                    break;
+                }
+            }
+        });
+    });
+
+    if let Err(e) = &*read_result.lock().unwrap() {
+        return Err(io::Error::new(e.kind(), e.to_string()));
+    }
+
+    let targets = labels
+        .into_iter()
+        .zip(errors)
+        .map(|(label, error)| TargetOutcome {
+            label,
+            result: match error.into_inner().unwrap() {
+                Some(e) => Err(e),
+                None => Ok(()),
+            },
+        })
+        .collect();
+
+    Ok(MultiCopyReport {
+        targets,
+        digests: digests.into_inner().unwrap(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let mut path = std::env::temp_dir();
+        path.push(format!("dd-rs-test-{name}-{}", process::id()));
+        path
+    }
+
+    #[test]
+    fn write_sparse_reproduces_input_including_trailing_hole() {
+        let path = temp_path("write-sparse");
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+        let mut output = OutputSource::File(file);
+
+        let mut data = vec![1u8, 2, 3];
+        data.extend(std::iter::repeat_n(0u8, 16));
+
+        write_sparse(&mut output, &data, 8).unwrap();
+        if let OutputSource::File(file) = &output {
+            file.set_len(data.len() as u64).unwrap();
+        }
+        drop(output);
+
+        let mut readback = Vec::new();
+        File::open(&path)
+            .unwrap()
+            .read_to_end(&mut readback)
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(readback, data);
+    }
+
+    /// Builds a minimal Android sparse image with one chunk of each of RAW,
+    /// FILL, and DONT_CARE, using a 4-byte block size for a small test file.
+    fn write_android_sparse_image(path: &PathBuf, raw_blocks: &[u8], fill_word: [u8; 4], dont_care_blocks: u32) {
+        const BLK_SZ: u32 = 4;
+        let raw_chunk_blocks = raw_blocks.len() as u32 / BLK_SZ;
+
+        let mut image = Vec::new();
+        image.extend_from_slice(&ANDROID_SPARSE_MAGIC.to_le_bytes());
+        image.extend_from_slice(&1u16.to_le_bytes()); // major version
+        image.extend_from_slice(&0u16.to_le_bytes()); // minor version
+        image.extend_from_slice(&28u16.to_le_bytes()); // file_hdr_sz
+        image.extend_from_slice(&12u16.to_le_bytes()); // chunk_hdr_sz
+        image.extend_from_slice(&BLK_SZ.to_le_bytes());
+        image.extend_from_slice(&(raw_chunk_blocks + 1 + dont_care_blocks).to_le_bytes()); // total_blks: raw + 1 fill + dont_care
+        image.extend_from_slice(&3u32.to_le_bytes()); // total_chunks: raw, fill, dont_care
+        image.extend_from_slice(&0u32.to_le_bytes()); // image_checksum
+
+        // RAW chunk: payload copied straight through.
+        image.extend_from_slice(&ANDROID_CHUNK_TYPE_RAW.to_le_bytes());
+        image.extend_from_slice(&0u16.to_le_bytes()); // reserved
+        image.extend_from_slice(&raw_chunk_blocks.to_le_bytes());
+        image.extend_from_slice(&(12 + raw_blocks.len() as u32).to_le_bytes()); // total_sz
+        image.extend_from_slice(raw_blocks);
+
+        // FILL chunk: one block made of `fill_word` repeated.
+        image.extend_from_slice(&ANDROID_CHUNK_TYPE_FILL.to_le_bytes());
+        image.extend_from_slice(&0u16.to_le_bytes());
+        image.extend_from_slice(&1u32.to_le_bytes());
+        image.extend_from_slice(&16u32.to_le_bytes()); // total_sz: 12-byte header + 4-byte word
+        image.extend_from_slice(&fill_word);
+
+        // DONT_CARE chunk: expands to zeroed blocks with no payload.
+        image.extend_from_slice(&ANDROID_CHUNK_TYPE_DONT_CARE.to_le_bytes());
+        image.extend_from_slice(&0u16.to_le_bytes());
+        image.extend_from_slice(&dont_care_blocks.to_le_bytes());
+        image.extend_from_slice(&12u32.to_le_bytes());
+
+        std::fs::write(path, &image).unwrap();
+    }
+
+    #[test]
+    fn android_sparse_reader_expands_raw_fill_and_dont_care_chunks() {
+        let path = temp_path("android-sparse");
+        let raw_blocks = [1u8, 2, 3, 4, 5, 6, 7, 8];
+        let fill_word = [0xAAu8, 0xBB, 0xCC, 0xDD];
+        write_android_sparse_image(&path, &raw_blocks, fill_word, 2);
+
+        let file = File::open(&path).unwrap();
+        let mut reader = AndroidSparseReader::new(file).unwrap();
+        assert_eq!(reader.expanded_size(), 5 * 4); // (2 raw + 1 fill + 2 dont_care) blocks * 4 bytes
+
+        let mut expanded = Vec::new();
+        reader.read_to_end(&mut expanded).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut expected = raw_blocks.to_vec();
+        expected.extend_from_slice(&fill_word);
+        expected.extend(std::iter::repeat_n(0u8, 8));
+
+        assert_eq!(expanded, expected);
+    }
+
+    #[test]
+    fn copy_with_callback_multi_fans_out_to_every_target() {
+        let src_path = temp_path("multi-src");
+        std::fs::write(&src_path, b"hello world, this is some fan-out test data").unwrap();
+        let input = InputSource::File(File::open(&src_path).unwrap());
+
+        let out1 = temp_path("multi-out1");
+        let out2 = temp_path("multi-out2");
+        let outputs = vec![
+            (
+                "out1".to_string(),
+                OutputSource::File(
+                    OpenOptions::new()
+                        .write(true)
+                        .create(true)
+                        .truncate(true)
+                        .open(&out1)
+                        .unwrap(),
+                ),
+            ),
+            (
+                "out2".to_string(),
+                OutputSource::File(
+                    OpenOptions::new()
+                        .write(true)
+                        .create(true)
+                        .truncate(true)
+                        .open(&out2)
+                        .unwrap(),
+                ),
+            ),
+        ];
+
+        // A small buffer relative to the input forces several recv/send round
+        // trips through the shared-buffer pool, exercising recycling.
+        let config = ThreadedCopyConfig {
+            buffer_size: 8,
+            buffer_count: 2,
+            sparse: false,
+            max_bytes: None,
+            hash_algorithms: Vec::new(),
+        };
+
+        let report = copy_with_callback_multi(input, outputs, config, |_| {}).unwrap();
+        assert!(report.targets.iter().all(|t| t.succeeded()));
+
+        let expected = std::fs::read(&src_path).unwrap();
+        assert_eq!(std::fs::read(&out1).unwrap(), expected);
+        assert_eq!(std::fs::read(&out2).unwrap(), expected);
+
+        std::fs::remove_file(&src_path).unwrap();
+        std::fs::remove_file(&out1).unwrap();
+        std::fs::remove_file(&out2).unwrap();
+    }
+
+    #[test]
+    fn compression_round_trips_through_wrap_writer_and_wrap_reader() {
+        let path = temp_path("compression-roundtrip");
+        let data = b"some disc-image bytes that should survive gzip round-tripping intact";
+
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+        let mut writer = Compression::Gzip.wrap_writer(file).unwrap();
+        writer.write_all(data).unwrap();
+        drop(writer);
+
+        let file = File::open(&path).unwrap();
+        let mut reader = Compression::Gzip.wrap_reader(file).unwrap();
+        let mut decompressed = Vec::new();
+        reader.read_to_end(&mut decompressed).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(decompressed, data);
+    }
+
+    #[test]
+    fn skip_input_and_seek_output_advance_by_byte_offset() {
+        let in_path = temp_path("skip-input");
+        std::fs::write(&in_path, b"0123456789").unwrap();
+        let mut input = InputSource::File(File::open(&in_path).unwrap());
+        skip_input(&mut input, 4, 4096).unwrap();
+        let mut rest = Vec::new();
+        input.read_to_end(&mut rest).unwrap();
+        std::fs::remove_file(&in_path).unwrap();
+        assert_eq!(rest, b"456789");
+
+        let out_path = temp_path("seek-output");
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&out_path)
+            .unwrap();
+        let mut output = OutputSource::File(file);
+        seek_output(&mut output, 4, 4096).unwrap();
+        output.write_all(b"XYZ").unwrap();
+        drop(output);
+
+        let written = std::fs::read(&out_path).unwrap();
+        std::fs::remove_file(&out_path).unwrap();
+        assert_eq!(&written[4..7], b"XYZ");
+    }
+
+    #[test]
+    fn copy_with_callback_count_caps_blocks_copied() {
+        let src_path = temp_path("count-src");
+        std::fs::write(&src_path, b"0123456789abcdef").unwrap();
+        let input = InputSource::File(File::open(&src_path).unwrap());
+
+        let out_path = temp_path("count-out");
+        let output = OutputSource::File(
+            OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&out_path)
+                .unwrap(),
+        );
+
+        let config = ThreadedCopyConfig {
+            buffer_size: 4,
+            buffer_count: 2,
+            sparse: false,
+            max_bytes: Some(8),
+            hash_algorithms: Vec::new(),
+        };
+
+        copy_with_callback(input, output, config, |_| {}).unwrap();
+
+        let written = std::fs::read(&out_path).unwrap();
+        std::fs::remove_file(&src_path).unwrap();
+        std::fs::remove_file(&out_path).unwrap();
+        assert_eq!(written, b"01234567");
+    }
+
+    #[test]
+    fn verify_written_digest_matches_hash_computed_during_copy() {
+        let src_path = temp_path("verify-src");
+        std::fs::write(&src_path, b"verify me please, this is the payload").unwrap();
+        let input = InputSource::File(File::open(&src_path).unwrap());
+
+        let out_path = temp_path("verify-out");
+        let output = OutputSource::File(
+            OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&out_path)
+                .unwrap(),
+        );
+
+        let config = ThreadedCopyConfig {
+            buffer_size: 8,
+            buffer_count: 2,
+            sparse: false,
+            max_bytes: None,
+            hash_algorithms: vec![HashAlgorithm::Sha256],
+        };
+
+        let digests = copy_with_callback(input, output, config, |_| {}).unwrap();
+        let written_digest =
+            verify_written_digest(&out_path, HashAlgorithm::Sha256, None, 0, None).unwrap();
+        std::fs::remove_file(&src_path).unwrap();
+        std::fs::remove_file(&out_path).unwrap();
+
+        assert_eq!(digests[0].hex, written_digest);
+        assert!(check_verify_target(&written_digest, &digests[0].hex).unwrap());
+        assert!(!check_verify_target(&written_digest, "not-a-matching-hash").unwrap());
+    }
+
+    #[test]
+    fn verify_written_digest_honors_seek_offset() {
+        let out_path = temp_path("verify-seek-out");
+        std::fs::write(&out_path, b"leading-bytes-then-payload").unwrap();
+
+        let seek_bytes = b"leading-bytes-then-".len() as u64;
+        let mut hasher = HashAlgorithm::Sha256.build();
+        hasher.update(b"payload");
+        let expected = hasher.finalize_hex();
+
+        let computed =
+            verify_written_digest(&out_path, HashAlgorithm::Sha256, None, seek_bytes, None).unwrap();
+        std::fs::remove_file(&out_path).unwrap();
+
+        assert_eq!(computed, expected);
+    }
+
+    #[test]
+    fn verify_written_digest_decompresses_before_hashing() {
+        let src_path = temp_path("verify-compressed-src");
+        std::fs::write(&src_path, b"verify me please, this is the compressed payload").unwrap();
+        let input = InputSource::File(File::open(&src_path).unwrap());
+
+        let out_path = temp_path("verify-compressed-out");
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&out_path)
+            .unwrap();
+        let output = OutputSource::Compressed(Compression::Gzip.wrap_writer(file).unwrap());
+
+        let config = ThreadedCopyConfig {
+            buffer_size: 8,
+            buffer_count: 2,
+            sparse: false,
+            max_bytes: None,
+            hash_algorithms: vec![HashAlgorithm::Sha256],
+        };
+
+        let digests = copy_with_callback(input, output, config, |_| {}).unwrap();
+        let written_digest = verify_written_digest(
+            &out_path,
+            HashAlgorithm::Sha256,
+            None,
+            0,
+            Some(Compression::Gzip),
+        )
+        .unwrap();
+        std::fs::remove_file(&src_path).unwrap();
+        std::fs::remove_file(&out_path).unwrap();
+
+        assert_eq!(digests[0].hex, written_digest);
+    }
 }